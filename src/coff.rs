@@ -0,0 +1,315 @@
+use std::convert::TryFrom;
+use std::io::{Read, Seek};
+
+use crate::flags::{Characteristics, DllCharacteristics};
+use crate::utils::{read_u16, read_u32, read_u64, read_u8, remaining_len};
+use crate::{Machine, PEError, PEResult};
+
+/// The COFF File Header, found immediately after the `PE\0\0` signature.
+#[derive(Debug)]
+pub(crate) struct CoffHeader {
+    pub(crate) machine: Machine,
+    pub(crate) number_of_sections: u16,
+    pub(crate) time_date_stamp: u32,
+    pub(crate) pointer_to_symbol_table: u32,
+    pub(crate) number_of_symbols: u32,
+    pub(crate) size_of_optional_header: u16,
+    pub(crate) characteristics: Characteristics,
+}
+
+impl CoffHeader {
+    pub(crate) fn parse(buf: &mut dyn Read) -> PEResult<Self> {
+        let machine = Machine::try_from(read_u16(buf)?)?;
+        let number_of_sections = read_u16(buf)?;
+        let time_date_stamp = read_u32(buf)?;
+        let pointer_to_symbol_table = read_u32(buf)?;
+        let number_of_symbols = read_u32(buf)?;
+        let size_of_optional_header = read_u16(buf)?;
+        let characteristics = Characteristics(read_u16(buf)?);
+
+        Ok(CoffHeader {
+            machine,
+            number_of_sections,
+            time_date_stamp,
+            pointer_to_symbol_table,
+            number_of_symbols,
+            size_of_optional_header,
+            characteristics,
+        })
+    }
+}
+
+/// The subsystem required to run this image, as found in the Optional Header.
+#[derive(Debug)]
+#[repr(u16)]
+pub(crate) enum Subsystem {
+    /// An unknown subsystem
+    Unknown = 0,
+    /// Device drivers and native Windows processes
+    Native = 1,
+    /// The Windows graphical user interface (GUI) subsystem
+    WindowsGui = 2,
+    /// The Windows character subsystem
+    WindowsCui = 3,
+    /// The OS/2 character subsystem
+    Os2Cui = 5,
+    /// The Posix character subsystem
+    PosixCui = 7,
+    /// Native Win9x driver
+    NativeWindows = 8,
+    /// Windows CE
+    WindowsCeGui = 9,
+    /// An Extensible Firmware Interface (EFI) application
+    EfiApplication = 10,
+    /// An EFI driver with boot services
+    EfiBootServiceDriver = 11,
+    /// An EFI driver with run-time services
+    EfiRuntimeDriver = 12,
+    /// An EFI ROM image
+    EfiRom = 13,
+    /// XBOX
+    Xbox = 14,
+    /// Windows boot application
+    WindowsBootApplication = 16,
+}
+
+impl TryFrom<u16> for Subsystem {
+    type Error = PEError;
+    fn try_from(n: u16) -> PEResult<Subsystem> {
+        match n {
+            0 => Ok(Subsystem::Unknown),
+            1 => Ok(Subsystem::Native),
+            2 => Ok(Subsystem::WindowsGui),
+            3 => Ok(Subsystem::WindowsCui),
+            5 => Ok(Subsystem::Os2Cui),
+            7 => Ok(Subsystem::PosixCui),
+            8 => Ok(Subsystem::NativeWindows),
+            9 => Ok(Subsystem::WindowsCeGui),
+            10 => Ok(Subsystem::EfiApplication),
+            11 => Ok(Subsystem::EfiBootServiceDriver),
+            12 => Ok(Subsystem::EfiRuntimeDriver),
+            13 => Ok(Subsystem::EfiRom),
+            14 => Ok(Subsystem::Xbox),
+            16 => Ok(Subsystem::WindowsBootApplication),
+            _ => Err(PEError::InvalidSubsystem(n)),
+        }
+    }
+}
+
+/// An entry in the data directory array, giving the RVA and size of a table
+/// such as the import or export table.
+#[derive(Debug)]
+pub(crate) struct DataDirectory {
+    pub(crate) virtual_address: u32,
+    pub(crate) size: u32,
+}
+
+/// Each `DataDirectory` entry is a fixed 8 bytes on disk.
+const DATA_DIRECTORY_SIZE: u64 = 8;
+
+/// The Optional Header. Despite the name, this is required for image files.
+///
+/// Dispatched on `magic`: `0x10b` is PE32 (32-bit `image_base`, with an extra
+/// `base_of_data` field not present in PE32+), `0x20b` is PE32+ (64-bit
+/// `image_base`, no `base_of_data`).
+#[derive(Debug)]
+pub(crate) struct OptionalHeader {
+    pub(crate) magic: u16,
+    pub(crate) major_linker_version: u8,
+    pub(crate) minor_linker_version: u8,
+    pub(crate) size_of_code: u32,
+    pub(crate) size_of_initialized_data: u32,
+    pub(crate) size_of_uninitialized_data: u32,
+    pub(crate) address_of_entry_point: u32,
+    pub(crate) base_of_code: u32,
+    /// Only present in PE32. Absent (`None`) in PE32+.
+    pub(crate) base_of_data: Option<u32>,
+    pub(crate) image_base: u64,
+    pub(crate) section_alignment: u32,
+    pub(crate) file_alignment: u32,
+    pub(crate) major_os_version: u16,
+    pub(crate) minor_os_version: u16,
+    pub(crate) major_image_version: u16,
+    pub(crate) minor_image_version: u16,
+    pub(crate) major_subsystem_version: u16,
+    pub(crate) minor_subsystem_version: u16,
+    pub(crate) win32_version_value: u32,
+    pub(crate) size_of_image: u32,
+    pub(crate) size_of_headers: u32,
+    pub(crate) checksum: u32,
+    pub(crate) subsystem: Subsystem,
+    pub(crate) dll_characteristics: DllCharacteristics,
+    pub(crate) size_of_stack_reserve: u64,
+    pub(crate) size_of_stack_commit: u64,
+    pub(crate) size_of_heap_reserve: u64,
+    pub(crate) size_of_heap_commit: u64,
+    pub(crate) loader_flags: u32,
+    pub(crate) number_of_rva_and_sizes: u32,
+    pub(crate) data_directories: Vec<DataDirectory>,
+}
+
+impl OptionalHeader {
+    pub(crate) fn parse(buf: &mut (impl Read + Seek)) -> PEResult<Self> {
+        let magic = read_u16(buf)?;
+        let major_linker_version = read_u8(buf)?;
+        let minor_linker_version = read_u8(buf)?;
+        let size_of_code = read_u32(buf)?;
+        let size_of_initialized_data = read_u32(buf)?;
+        let size_of_uninitialized_data = read_u32(buf)?;
+        let address_of_entry_point = read_u32(buf)?;
+        let base_of_code = read_u32(buf)?;
+
+        let (base_of_data, image_base) = match magic {
+            0x10b => {
+                let base_of_data = read_u32(buf)?;
+                let image_base = u64::from(read_u32(buf)?);
+                (Some(base_of_data), image_base)
+            }
+            0x20b => (None, read_u64(buf)?),
+            _ => return Err(PEError::InvalidOptionalHeaderMagic(magic)),
+        };
+
+        let section_alignment = read_u32(buf)?;
+        let file_alignment = read_u32(buf)?;
+        let major_os_version = read_u16(buf)?;
+        let minor_os_version = read_u16(buf)?;
+        let major_image_version = read_u16(buf)?;
+        let minor_image_version = read_u16(buf)?;
+        let major_subsystem_version = read_u16(buf)?;
+        let minor_subsystem_version = read_u16(buf)?;
+        let win32_version_value = read_u32(buf)?;
+        let size_of_image = read_u32(buf)?;
+        let size_of_headers = read_u32(buf)?;
+        let checksum = read_u32(buf)?;
+        let subsystem = Subsystem::try_from(read_u16(buf)?)?;
+        let dll_characteristics = DllCharacteristics(read_u16(buf)?);
+
+        // PE32 stores these as 32-bit fields; PE32+ widens them to 64-bit.
+        let (
+            size_of_stack_reserve,
+            size_of_stack_commit,
+            size_of_heap_reserve,
+            size_of_heap_commit,
+        ) = if magic == 0x10b {
+            (
+                u64::from(read_u32(buf)?),
+                u64::from(read_u32(buf)?),
+                u64::from(read_u32(buf)?),
+                u64::from(read_u32(buf)?),
+            )
+        } else {
+            (
+                read_u64(buf)?,
+                read_u64(buf)?,
+                read_u64(buf)?,
+                read_u64(buf)?,
+            )
+        };
+
+        let loader_flags = read_u32(buf)?;
+        let number_of_rva_and_sizes = read_u32(buf)?;
+
+        // `number_of_rva_and_sizes` is attacker-controlled; cap the upfront
+        // allocation against what the buffer could actually hold.
+        let max_entries = remaining_len(buf)? / DATA_DIRECTORY_SIZE;
+        let capacity = u64::from(number_of_rva_and_sizes).min(max_entries) as usize;
+        let mut data_directories = Vec::with_capacity(capacity);
+        for _ in 0..number_of_rva_and_sizes {
+            data_directories.push(DataDirectory {
+                virtual_address: read_u32(buf)?,
+                size: read_u32(buf)?,
+            });
+        }
+
+        Ok(OptionalHeader {
+            magic,
+            major_linker_version,
+            minor_linker_version,
+            size_of_code,
+            size_of_initialized_data,
+            size_of_uninitialized_data,
+            address_of_entry_point,
+            base_of_code,
+            base_of_data,
+            image_base,
+            section_alignment,
+            file_alignment,
+            major_os_version,
+            minor_os_version,
+            major_image_version,
+            minor_image_version,
+            major_subsystem_version,
+            minor_subsystem_version,
+            win32_version_value,
+            size_of_image,
+            size_of_headers,
+            checksum,
+            subsystem,
+            dll_characteristics,
+            size_of_stack_reserve,
+            size_of_stack_commit,
+            size_of_heap_reserve,
+            size_of_heap_commit,
+            loader_flags,
+            number_of_rva_and_sizes,
+            data_directories,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+
+    /// A minimal PE32 optional header, truncated right after
+    /// `number_of_rva_and_sizes` (i.e. no data directories actually follow),
+    /// with that count set to a huge, attacker-controlled value.
+    fn truncated_optional_header_with_huge_rva_count() -> Vec<u8> {
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(&0x10b_u16.to_le_bytes()); // magic (PE32)
+        bytes.push(0); // major_linker_version
+        bytes.push(0); // minor_linker_version
+        bytes.extend_from_slice(&0_u32.to_le_bytes()); // size_of_code
+        bytes.extend_from_slice(&0_u32.to_le_bytes()); // size_of_initialized_data
+        bytes.extend_from_slice(&0_u32.to_le_bytes()); // size_of_uninitialized_data
+        bytes.extend_from_slice(&0_u32.to_le_bytes()); // address_of_entry_point
+        bytes.extend_from_slice(&0_u32.to_le_bytes()); // base_of_code
+        bytes.extend_from_slice(&0_u32.to_le_bytes()); // base_of_data
+        bytes.extend_from_slice(&0_u32.to_le_bytes()); // image_base
+        bytes.extend_from_slice(&0_u32.to_le_bytes()); // section_alignment
+        bytes.extend_from_slice(&0_u32.to_le_bytes()); // file_alignment
+        bytes.extend_from_slice(&0_u16.to_le_bytes()); // major_os_version
+        bytes.extend_from_slice(&0_u16.to_le_bytes()); // minor_os_version
+        bytes.extend_from_slice(&0_u16.to_le_bytes()); // major_image_version
+        bytes.extend_from_slice(&0_u16.to_le_bytes()); // minor_image_version
+        bytes.extend_from_slice(&0_u16.to_le_bytes()); // major_subsystem_version
+        bytes.extend_from_slice(&0_u16.to_le_bytes()); // minor_subsystem_version
+        bytes.extend_from_slice(&0_u32.to_le_bytes()); // win32_version_value
+        bytes.extend_from_slice(&0_u32.to_le_bytes()); // size_of_image
+        bytes.extend_from_slice(&0_u32.to_le_bytes()); // size_of_headers
+        bytes.extend_from_slice(&0_u32.to_le_bytes()); // checksum
+        bytes.extend_from_slice(&2_u16.to_le_bytes()); // subsystem (WindowsGui)
+        bytes.extend_from_slice(&0_u16.to_le_bytes()); // dll_characteristics
+        bytes.extend_from_slice(&0_u32.to_le_bytes()); // size_of_stack_reserve
+        bytes.extend_from_slice(&0_u32.to_le_bytes()); // size_of_stack_commit
+        bytes.extend_from_slice(&0_u32.to_le_bytes()); // size_of_heap_reserve
+        bytes.extend_from_slice(&0_u32.to_le_bytes()); // size_of_heap_commit
+        bytes.extend_from_slice(&0_u32.to_le_bytes()); // loader_flags
+        bytes.extend_from_slice(&u32::MAX.to_le_bytes()); // number_of_rva_and_sizes
+        bytes
+    }
+
+    #[test]
+    fn parse_caps_data_directory_preallocation_against_remaining_buffer() {
+        let bytes = truncated_optional_header_with_huge_rva_count();
+        let mut buf = Cursor::new(bytes.as_slice());
+
+        // `number_of_rva_and_sizes` claims ~4 billion entries but the buffer
+        // has none left; this must fail cleanly with an EOF-flavored error
+        // instead of attempting a multi-gigabyte allocation.
+        let result = OptionalHeader::parse(&mut buf);
+        assert!(matches!(result, Err(PEError::IoError(_))));
+    }
+}
@@ -0,0 +1,158 @@
+use std::io::{Read, Seek, SeekFrom};
+
+use crate::coff::DataDirectory;
+use crate::section::{self, SectionHeader};
+use crate::utils::{read_cstring, read_u16, read_u32, remaining_len};
+use crate::PEResult;
+
+/// Index of the debug directory within the Optional Header's data
+/// directories array.
+const DEBUG_DIRECTORY_INDEX: usize = 6;
+
+/// `IMAGE_DEBUG_DIRECTORY` is a fixed 28 bytes on disk.
+const DEBUG_DIRECTORY_ENTRY_SIZE: u32 = 28;
+
+/// `IMAGE_DEBUG_TYPE_CODEVIEW`
+const IMAGE_DEBUG_TYPE_CODEVIEW: u32 = 2;
+
+/// The CodeView signature used by the modern (PDB 7.0) debug format.
+const RSDS_SIGNATURE: &[u8; 4] = b"RSDS";
+
+/// A single `IMAGE_DEBUG_DIRECTORY` entry.
+#[derive(Debug)]
+struct DebugDirectoryEntry {
+    characteristics: u32,
+    time_date_stamp: u32,
+    major_version: u16,
+    minor_version: u16,
+    kind: u32,
+    size_of_data: u32,
+    address_of_raw_data: u32,
+    pointer_to_raw_data: u32,
+}
+
+impl DebugDirectoryEntry {
+    fn parse(buf: &mut dyn Read) -> PEResult<Self> {
+        let characteristics = read_u32(buf)?;
+        let time_date_stamp = read_u32(buf)?;
+        let major_version = read_u16(buf)?;
+        let minor_version = read_u16(buf)?;
+        let kind = read_u32(buf)?;
+        let size_of_data = read_u32(buf)?;
+        let address_of_raw_data = read_u32(buf)?;
+        let pointer_to_raw_data = read_u32(buf)?;
+
+        Ok(DebugDirectoryEntry {
+            characteristics,
+            time_date_stamp,
+            major_version,
+            minor_version,
+            kind,
+            size_of_data,
+            address_of_raw_data,
+            pointer_to_raw_data,
+        })
+    }
+}
+
+/// The CodeView debug info record (RSDS format), used by PDB 7.0 symbol
+/// servers to uniquely identify the PDB matching a binary.
+#[derive(Debug)]
+pub(crate) struct CodeView {
+    pub(crate) guid: [u8; 16],
+    pub(crate) age: u32,
+    pub(crate) path: String,
+}
+
+impl CodeView {
+    /// Parses the CodeView header at the reader's current position. Returns
+    /// `Ok(None)` if the signature isn't `RSDS` (e.g. the older NB10 format),
+    /// since that's the only layout this parser understands.
+    fn parse(buf: &mut dyn Read) -> PEResult<Option<Self>> {
+        let mut signature = [0_u8; 4];
+        buf.read_exact(&mut signature)?;
+
+        if &signature != RSDS_SIGNATURE {
+            return Ok(None);
+        }
+
+        let mut guid = [0_u8; 16];
+        buf.read_exact(&mut guid)?;
+
+        let age = read_u32(buf)?;
+        let path = read_cstring(buf)?;
+
+        Ok(Some(CodeView { guid, age, path }))
+    }
+}
+
+/// Reads the debug data directory and returns the CodeView (RSDS) record, if
+/// present, giving the PDB path and build GUID for the image.
+pub(crate) fn parse_debug_info<R: Read + Seek>(
+    buf: &mut R,
+    data_directories: &[DataDirectory],
+    sections: &[SectionHeader],
+) -> PEResult<Option<CodeView>> {
+    let directory = match data_directories.get(DEBUG_DIRECTORY_INDEX) {
+        Some(directory) if directory.size > 0 => directory,
+        _ => return Ok(None),
+    };
+
+    let offset = section::rva_to_offset(sections, directory.virtual_address);
+    buf.seek(SeekFrom::Start(offset))?;
+
+    let entry_count = directory.size / DEBUG_DIRECTORY_ENTRY_SIZE;
+
+    // `directory.size` is attacker-controlled; cap the upfront allocation
+    // against what the buffer could actually hold.
+    let max_entries = remaining_len(buf)? / u64::from(DEBUG_DIRECTORY_ENTRY_SIZE);
+    let capacity = u64::from(entry_count).min(max_entries) as usize;
+    let mut entries = Vec::with_capacity(capacity);
+    for _ in 0..entry_count {
+        entries.push(DebugDirectoryEntry::parse(buf)?);
+    }
+
+    for entry in entries {
+        if entry.kind != IMAGE_DEBUG_TYPE_CODEVIEW {
+            continue;
+        }
+
+        buf.seek(SeekFrom::Start(u64::from(entry.pointer_to_raw_data)))?;
+
+        if let Some(codeview) = CodeView::parse(buf)? {
+            return Ok(Some(codeview));
+        }
+    }
+
+    Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+
+    #[test]
+    fn parse_debug_info_caps_entry_preallocation_against_remaining_buffer() {
+        // A data directory claiming an enormous size (and so an enormous
+        // entry count), pointing at an offset with no bytes actually behind
+        // it. This must fail cleanly instead of attempting a multi-gigabyte
+        // allocation or panicking on the out-of-bounds seek.
+        let data_directories: Vec<DataDirectory> = (0..DEBUG_DIRECTORY_INDEX)
+            .map(|_| DataDirectory {
+                virtual_address: 0,
+                size: 0,
+            })
+            .chain(std::iter::once(DataDirectory {
+                virtual_address: 1000,
+                size: u32::MAX,
+            }))
+            .collect();
+
+        let mut buf = Cursor::new([0_u8; 8].as_slice());
+
+        let result = parse_debug_info(&mut buf, &data_directories, &[]);
+        assert!(result.is_err());
+    }
+}
@@ -0,0 +1,193 @@
+use std::fmt;
+
+/// Declares a bitflags-style wrapper around an integer type: a newtype with
+/// one associated const per flag, `contains`/`iter`, and a `Debug` impl that
+/// lists the set flags by name instead of printing the raw bitmask.
+macro_rules! bitflags {
+    (
+        $(#[$meta:meta])*
+        pub(crate) struct $name:ident: $ty:ty {
+            $(
+                $(#[$fmeta:meta])*
+                const $flag:ident = $value:expr;
+            )*
+        }
+    ) => {
+        $(#[$meta])*
+        #[derive(Clone, Copy, PartialEq, Eq)]
+        pub(crate) struct $name(pub(crate) $ty);
+
+        #[allow(non_upper_case_globals)]
+        impl $name {
+            $(
+                $(#[$fmeta])*
+                pub(crate) const $flag: $name = $name($value);
+            )*
+
+            const ALL: &'static [(&'static str, $ty)] = &[
+                $((stringify!($flag), $value),)*
+            ];
+
+            /// Returns `true` if every bit set in `flag` is also set in `self`.
+            pub(crate) fn contains(&self, flag: $name) -> bool {
+                self.0 & flag.0 == flag.0
+            }
+
+            /// Iterates over the names of the known flags set in `self`.
+            pub(crate) fn iter(&self) -> impl Iterator<Item = &'static str> + '_ {
+                Self::ALL
+                    .iter()
+                    .filter(move |(_, bit)| self.0 & bit == *bit)
+                    .map(|(name, _)| *name)
+            }
+        }
+
+        impl fmt::Debug for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.debug_set().entries(self.iter()).finish()
+            }
+        }
+    };
+}
+
+bitflags! {
+    /// The Characteristics field of the COFF File Header, indicating
+    /// attributes of the image file.
+    pub(crate) struct Characteristics: u16 {
+        /// Image only, Windows CE, and Microsoft Windows NT and later. This indicates that the file does not contain base relocations and must therefore be loaded at its preferred base address. If the base address is not available, the loader reports an error. The default behavior of the linker is to strip base relocations from executable (EXE) files.
+        const RELOCS_STRIPPED = 0x0001;
+        /// Image only. This indicates that the image file is valid and can be run. If this flag is not set, it indicates a linker error.
+        const EXECUTABLE_IMAGE = 0x0002;
+        /// COFF line numbers have been removed. This flag is deprecated and should be zero.
+        const LINE_NUMS_STRIPPED = 0x0004;
+        /// COFF symbol table entries for local symbols have been removed. This flag is deprecated and should be zero.
+        const LOCAL_SYMS_STRIPPED = 0x0008;
+        /// Obsolete. Aggressively trim working set. This flag is deprecated for Windows 2000 and later and must be zero.
+        const AGGRESSIVE_WS_TRIM = 0x0010;
+        /// Application can handle > 2-GB addresses.
+        const LARGE_ADDRESS_AWARE = 0x0020;
+        /// This flag is reserved for future use.
+        const RESERVED = 0x0040;
+        /// Little endian: the least significant bit (LSB) precedes the most significant bit (MSB) in memory. This flag is deprecated and should be zero.
+        const BYTES_REVERSED_LO = 0x0080;
+        /// Machine is based on a 32-bit-word architecture.
+        const MACHINE_32BIT = 0x0100;
+        /// Debugging information is removed from the image file.
+        const DEBUG_STRIPPED = 0x0200;
+        /// If the image is on removable media, fully load it and copy it to the swap file.
+        const REMOVABLE_RUN_FROM_SWAP = 0x0400;
+        /// If the image is on network media, fully load it and copy it to the swap file.
+        const NET_RUN_FROM_SWAP = 0x0800;
+        /// The image file is a system file, not a user program.
+        const SYSTEM = 0x1000;
+        /// The image file is a dynamic-link library (DLL). Such files are considered executable files for almost all purposes, although they cannot be directly run.
+        const DLL = 0x2000;
+        /// The file should be run only on a uniprocessor machine.
+        const UP_SYSTEM_ONLY = 0x4000;
+        /// Big endian: the MSB precedes the LSB in memory. This flag is deprecated and should be zero.
+        const BYTES_REVERSED_HI = 0x8000;
+    }
+}
+
+bitflags! {
+    /// The Characteristics field of a section header (`IMAGE_SCN_*`),
+    /// describing its content and memory attributes.
+    pub(crate) struct SectionCharacteristics: u32 {
+        /// Reserved.
+        const TYPE_NO_PAD = 0x0000_0008;
+        /// The section contains executable code.
+        const CNT_CODE = 0x0000_0020;
+        /// The section contains initialized data.
+        const CNT_INITIALIZED_DATA = 0x0000_0040;
+        /// The section contains uninitialized data.
+        const CNT_UNINITIALIZED_DATA = 0x0000_0080;
+        /// Reserved.
+        const LNK_OTHER = 0x0000_0100;
+        /// The section contains comments or other information. Valid only for object files.
+        const LNK_INFO = 0x0000_0200;
+        /// The section will not become part of the image. Valid only for object files.
+        const LNK_REMOVE = 0x0000_0800;
+        /// The section contains COMDAT data. Valid only for object files.
+        const LNK_COMDAT = 0x0000_1000;
+        /// The section contains data referenced through the global pointer (GP).
+        const GPREL = 0x0000_8000;
+        /// Reserved, aliases `MEM_16BIT`.
+        const MEM_PURGEABLE = 0x0002_0000;
+        /// The section contains extended relocations.
+        const LNK_NRELOC_OVFL = 0x0100_0000;
+        /// The section can be discarded as needed.
+        const MEM_DISCARDABLE = 0x0200_0000;
+        /// The section cannot be cached.
+        const MEM_NOT_CACHED = 0x0400_0000;
+        /// The section is not pageable.
+        const MEM_NOT_PAGED = 0x0800_0000;
+        /// The section can be shared in memory.
+        const MEM_SHARED = 0x1000_0000;
+        /// The section can be executed as code.
+        const MEM_EXECUTE = 0x2000_0000;
+        /// The section can be read.
+        const MEM_READ = 0x4000_0000;
+        /// The section can be written to.
+        const MEM_WRITE = 0x8000_0000;
+    }
+}
+
+impl SectionCharacteristics {
+    /// Bits 20-23, the section's alignment. This is an enumerated 4-bit
+    /// field (one of 14 values), not a set of independent flags, so it's
+    /// decoded separately rather than through the generic OR-able bitflag
+    /// set above.
+    const ALIGNMENT_MASK: u32 = 0x00F0_0000;
+
+    /// The section's required alignment in bytes, decoded from the
+    /// alignment nibble. Returns `None` if the nibble doesn't match a known
+    /// alignment, including the all-zero "unspecified" case.
+    pub(crate) fn alignment(&self) -> Option<u32> {
+        match self.0 & Self::ALIGNMENT_MASK {
+            0x0010_0000 => Some(1),
+            0x0020_0000 => Some(2),
+            0x0030_0000 => Some(4),
+            0x0040_0000 => Some(8),
+            0x0050_0000 => Some(16),
+            0x0060_0000 => Some(32),
+            0x0070_0000 => Some(64),
+            0x0080_0000 => Some(128),
+            0x0090_0000 => Some(256),
+            0x00a0_0000 => Some(512),
+            0x00b0_0000 => Some(1024),
+            0x00c0_0000 => Some(2048),
+            0x00d0_0000 => Some(4096),
+            0x00e0_0000 => Some(8192),
+            _ => None,
+        }
+    }
+}
+
+bitflags! {
+    /// The DllCharacteristics field of the Optional Header, describing the
+    /// security mitigations an image supports.
+    pub(crate) struct DllCharacteristics: u16 {
+        /// ASLR with a 64-bit address space.
+        const HIGH_ENTROPY_VA = 0x0020;
+        /// The DLL can be relocated at load time (ASLR).
+        const DYNAMIC_BASE = 0x0040;
+        /// Code integrity checks are enforced.
+        const FORCE_INTEGRITY = 0x0080;
+        /// The image is compatible with data execution prevention (DEP/NX).
+        const NX_COMPAT = 0x0100;
+        /// The image is isolation aware, but do not isolate it.
+        const NO_ISOLATION = 0x0200;
+        /// The image does not use structured exception handling (SEH). No handlers can be called in this image.
+        const NO_SEH = 0x0400;
+        /// Do not bind the image.
+        const NO_BIND = 0x0800;
+        /// The image must execute in an AppContainer.
+        const APPCONTAINER = 0x1000;
+        /// A WDM driver.
+        const WDM_DRIVER = 0x2000;
+        /// The image supports Control Flow Guard.
+        const GUARD_CF = 0x4000;
+        /// The image is aware of terminal server.
+        const TERMINAL_SERVER_AWARE = 0x8000;
+    }
+}
@@ -1,20 +1,28 @@
-#![feature(bufreader_seek_relative)]
 #![allow(dead_code, unused_imports)]
 
 use std::{
     convert::TryFrom,
-    fs::File,
-    io::{self, BufReader, Read},
+    io::{self, Cursor, Read, Seek, SeekFrom},
     path::Path,
 };
 
+use coff::{CoffHeader, OptionalHeader};
+use debug::CodeView;
+use polyglot::Polyglot;
+use probe::FileKind;
+use section::SectionHeader;
 use utils::read_u32;
 
 const DOS_HEADER: [u8; 60] = [0_u8; 60];
 const PE_HEADER: &[u8; 4] = b"PE\0\0";
 
 mod coff;
+mod debug;
 mod dos;
+mod flags;
+mod polyglot;
+mod probe;
+mod section;
 mod utils;
 
 type PEResult<T> = Result<T, PEError>;
@@ -23,6 +31,10 @@ type PEResult<T> = Result<T, PEError>;
 enum PEError {
     IoError(io::Error),
     InvalidMachineType(u16),
+    InvalidSubsystem(u16),
+    InvalidOptionalHeaderMagic(u16),
+    InvalidPeSignature([u8; 4]),
+    UnexpectedEof,
 }
 
 impl From<io::Error> for PEError {
@@ -32,7 +44,24 @@ impl From<io::Error> for PEError {
 }
 
 #[derive(Debug)]
-struct PortableExecutable {}
+struct PortableExecutable {
+    coff_header: CoffHeader,
+    optional_header: OptionalHeader,
+    sections: Vec<SectionHeader>,
+    debug_info: Option<CodeView>,
+}
+
+impl PortableExecutable {
+    fn rva_to_offset(&self, rva: u32) -> Option<u64> {
+        Some(section::rva_to_offset(&self.sections, rva))
+    }
+
+    /// The PDB path and build GUID parsed from the debug data directory, if
+    /// the image has one.
+    fn debug_info(&self) -> Option<&CodeView> {
+        self.debug_info.as_ref()
+    }
+}
 
 /// The machine field has one of the following values that specifies its CPU type.
 ///
@@ -126,56 +155,65 @@ impl TryFrom<u16> for Machine {
     }
 }
 
-enum Characteristics {
-    /// Image only, Windows CE, and Microsoft Windows NT and later. This indicates that the file does not contain base relocations and must therefore be loaded at its preferred base address. If the base address is not available, the loader reports an error. The default behavior of the linker is to strip base relocations from executable (EXE) files.
-    RelocsStripped = 0x0001,
-    /// Image only. This indicates that the image file is valid and can be run. If this flag is not set, it indicates a linker error.
-    ExecutableImage = 0x0002,
-    /// COFF line numbers have been removed. This flag is deprecated and should be zero.
-    LineNumsStripped = 0x0004,
-    /// COFF symbol table entries for local symbols have been removed. This flag is deprecated and should be zero.
-    LocalSymsStripped = 0x0008,
-    /// Obsolete. Aggressively trim working set. This flag is deprecated for Windows 2000 and later and must be zero.
-    AggressiveWsTrim = 0x0010,
-    /// Application can handle > 2-GB addresses.
-    LargeAddressAware = 0x0020,
-    /// This flag is reserved for future use.
-    Reserved = 0x0040,
-    /// Little endian: the least significant bit (LSB) precedes the most significant bit (MSB) in memory. This flag is deprecated and should be zero.
-    BytesReversedLo = 0x0080,
-    /// Machine is based on a 32-bit-word architecture.
-    Machine32Bit = 0x0100,
-    /// Debugging information is removed from the image file.
-    DebugStripped = 0x0200,
-    /// If the image is on removable media, fully load it and copy it to the swap file.
-    RemovableRunFromSwap = 0x0400,
-    /// If the image is on network media, fully load it and copy it to the swap file.
-    NetRunFromSwap = 0x0800,
-    /// The image file is a system file, not a user program.
-    System = 0x1000,
-    /// The image file is a dynamic-link library (DLL). Such files are considered executable files for almost all purposes, although they cannot be directly run.
-    Dll = 0x2000,
-    /// The file should be run only on a uniprocessor machine.
-    UpSystemOnly = 0x4000,
-    /// Big endian: the MSB precedes the LSB in memory. This flag is deprecated and should be zero.
-    BytesReversedHi = 0x8000,
-}
-
 struct Parser {}
 
 impl Parser {
+    /// Reads the file at `path` into memory and parses it. Most callers
+    /// should prefer [`Parser::parse`] directly when the bytes are already
+    /// in memory (e.g. downloaded, or mapped from another process).
     fn parse_pe<P: AsRef<Path>>(path: P) -> PEResult<PortableExecutable> {
-        let mut buf = BufReader::new(File::open(path)?);
+        let bytes = std::fs::read(path)?;
+
+        Self::parse(&bytes)
+    }
+
+    /// Cheaply classifies `bytes` as a last resort before giving up on a
+    /// file, without the cost of a full parse.
+    fn probe(bytes: &[u8]) -> FileKind {
+        probe::probe(bytes)
+    }
+
+    /// Scans `bytes` for the "Actually Portable Executable" polyglot
+    /// scheme, where a single file is simultaneously a valid MZ/PE and an
+    /// embedded shell script, ELF, and/or Mach-O. Unlike [`Parser::parse`],
+    /// this never fails: malformed input just yields no markers.
+    fn scan_polyglot(bytes: &[u8]) -> Polyglot {
+        polyglot::scan(bytes)
+    }
+
+    /// Parses a PE image directly out of an in-memory buffer, without
+    /// touching the filesystem.
+    fn parse(bytes: &[u8]) -> PEResult<PortableExecutable> {
+        let mut buf = Cursor::new(bytes);
 
         buf.read_exact(&mut DOS_HEADER)?;
 
         let ptr = read_u32(&mut buf)?;
 
-        dbg!(ptr);
-        buf.seek_relative(i64::from(ptr) - 64)?;
-        assert_eq!(&read_bytes_to_buffer!(buf, 4), PE_HEADER);
+        buf.seek(SeekFrom::Current(i64::from(ptr) - 64))?;
+
+        let signature = read_bytes_to_buffer!(buf, 4);
+        if &signature != PE_HEADER {
+            return Err(PEError::InvalidPeSignature(signature));
+        }
+
+        let coff_header = CoffHeader::parse(&mut buf)?;
+        let optional_header = OptionalHeader::parse(&mut buf)?;
+
+        let mut sections = Vec::with_capacity(coff_header.number_of_sections as usize);
+        for _ in 0..coff_header.number_of_sections {
+            sections.push(SectionHeader::parse(&mut buf)?);
+        }
+
+        let debug_info =
+            debug::parse_debug_info(&mut buf, &optional_header.data_directories, &sections)?;
 
-        Ok(PortableExecutable {})
+        Ok(PortableExecutable {
+            coff_header,
+            optional_header,
+            sections,
+            debug_info,
+        })
     }
 }
 
@@ -0,0 +1,93 @@
+use crate::utils::read_u32_at;
+
+/// Offset of `e_lfanew` within the DOS header.
+const E_LFANEW_OFFSET: usize = 0x3c;
+
+/// Start of the DOS stub region, immediately after the fixed DOS header.
+const DOS_STUB_START: usize = 0x40;
+
+const ELF_MAGIC: &[u8; 4] = b"\x7fELF";
+/// `MH_MAGIC_64` (`0xFEEDFACF`) as it's actually laid out on disk for a
+/// little-endian (x86_64/arm64) Mach-O, the architectures Cosmopolitan APE
+/// embeds.
+const MACHO_MAGIC_64: [u8; 4] = [0xcf, 0xfa, 0xed, 0xfe];
+/// `FAT_MAGIC` is always big-endian per spec.
+const MACHO_MAGIC_FAT: [u8; 4] = [0xca, 0xfe, 0xba, 0xbe];
+
+/// The result of scanning a file for the "Actually Portable Executable"
+/// (APE) polyglot scheme, where a single file is simultaneously a valid
+/// MZ/PE, a POSIX shell script, and one or more embedded Unix executables.
+#[derive(Debug)]
+pub(crate) struct Polyglot {
+    pub(crate) is_pe: bool,
+    pub(crate) has_elf_header: bool,
+    pub(crate) has_macho_header: bool,
+    pub(crate) has_shell_stub: bool,
+    pub(crate) elf_offset: Option<usize>,
+    pub(crate) macho_offset: Option<usize>,
+}
+
+/// Scans `bytes` for the polyglot markers. Never panics on malformed or
+/// truncated input.
+pub(crate) fn scan(bytes: &[u8]) -> Polyglot {
+    let is_pe = is_pe_file(bytes);
+
+    let stub_end = read_u32_at(bytes, E_LFANEW_OFFSET)
+        .map(|e_lfanew| e_lfanew as usize)
+        .unwrap_or(bytes.len())
+        .min(bytes.len())
+        .max(DOS_STUB_START);
+
+    let stub = bytes.get(DOS_STUB_START..stub_end).unwrap_or(&[]);
+
+    let has_shell_stub = has_shell_trampoline(bytes);
+    let elf_offset = find(stub, ELF_MAGIC).map(|offset| DOS_STUB_START + offset);
+    let macho_offset = find(stub, &MACHO_MAGIC_64)
+        .or_else(|| find(stub, &MACHO_MAGIC_FAT))
+        .map(|offset| DOS_STUB_START + offset);
+
+    Polyglot {
+        is_pe,
+        has_elf_header: elf_offset.is_some(),
+        has_macho_header: macho_offset.is_some(),
+        has_shell_stub,
+        elf_offset,
+        macho_offset,
+    }
+}
+
+fn is_pe_file(bytes: &[u8]) -> bool {
+    if bytes.get(0..2) != Some(b"MZ".as_slice()) {
+        return false;
+    }
+
+    let e_lfanew = match read_u32_at(bytes, E_LFANEW_OFFSET) {
+        Ok(e_lfanew) => e_lfanew as usize,
+        Err(_) => return false,
+    };
+
+    bytes.get(e_lfanew..e_lfanew + 4) == Some(crate::PE_HEADER.as_slice())
+}
+
+/// APE's shell trampoline varies in its exact prefix (`MZqFpD`, `jartsr`,
+/// ...) across cosmopolitan libc versions, but it always starts at the real
+/// `MZ` signature and is a run of printable ASCII ending in a newline, so
+/// sniff for that shape rather than any one literal prefix.
+fn has_shell_trampoline(bytes: &[u8]) -> bool {
+    if bytes.get(0..2) != Some(b"MZ".as_slice()) {
+        return false;
+    }
+
+    let window = bytes.get(2..DOS_STUB_START.min(bytes.len())).unwrap_or(&[]);
+
+    match window.iter().position(|&b| b == b'\n') {
+        Some(newline) => window[..newline]
+            .iter()
+            .all(|&b| b.is_ascii_graphic() || b == b' ' || b == b'\t'),
+        None => false,
+    }
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).position(|w| w == needle)
+}
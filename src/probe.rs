@@ -0,0 +1,60 @@
+use crate::utils::{read_u16_at, read_u32_at};
+
+/// Offset of `e_lfanew` within the DOS header.
+const E_LFANEW_OFFSET: usize = 0x3c;
+
+/// Size of the `PE\0\0` signature plus the fixed 20-byte COFF File Header
+/// that immediately follows it, i.e. the offset of the Optional Header
+/// (and its magic word) relative to the start of the PE header.
+const OPTIONAL_HEADER_OFFSET: usize = 4 + 20;
+
+const MZ_SIGNATURE: &[u8; 2] = b"MZ";
+const PE_SIGNATURE: &[u8; 4] = b"PE\0\0";
+const BEOS_PEF_SIGNATURE: &[u8; 8] = b"Joy!peff";
+
+/// A cheap, best-effort classification of a file's executable format.
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) enum FileKind {
+    /// Doesn't start with a recognized executable signature at all.
+    NotExecutable,
+    /// A bare DOS MZ executable with no PE header.
+    DosExe,
+    /// A PE32 image.
+    Pe,
+    /// A PE32+ (64-bit) image.
+    Pe32Plus,
+    /// A BeOS Preferred Executable Format image.
+    BeOsPef,
+    /// Has a DOS/PE signature but the Optional Header magic isn't one this
+    /// parser understands.
+    Unknown,
+}
+
+/// Classifies `bytes` as cheaply as possible: this only ever reads the
+/// leading BeOS PEF or `MZ` signature, follows `e_lfanew` to peek for
+/// `PE\0\0`, and peeks the Optional Header magic. It never panics on malformed or truncated input,
+/// making it safe to run as a triage step over arbitrary, untrusted files.
+pub(crate) fn probe(bytes: &[u8]) -> FileKind {
+    if bytes.get(0..BEOS_PEF_SIGNATURE.len()) == Some(BEOS_PEF_SIGNATURE.as_slice()) {
+        return FileKind::BeOsPef;
+    }
+
+    if bytes.get(0..2) != Some(MZ_SIGNATURE.as_slice()) {
+        return FileKind::NotExecutable;
+    }
+
+    let e_lfanew = match read_u32_at(bytes, E_LFANEW_OFFSET) {
+        Ok(e_lfanew) => e_lfanew as usize,
+        Err(_) => return FileKind::DosExe,
+    };
+
+    if bytes.get(e_lfanew..e_lfanew + PE_SIGNATURE.len()) != Some(PE_SIGNATURE.as_slice()) {
+        return FileKind::DosExe;
+    }
+
+    match read_u16_at(bytes, e_lfanew + OPTIONAL_HEADER_OFFSET) {
+        Ok(0x10b) => FileKind::Pe,
+        Ok(0x20b) => FileKind::Pe32Plus,
+        _ => FileKind::Unknown,
+    }
+}
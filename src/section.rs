@@ -0,0 +1,113 @@
+use std::io::Read;
+
+use crate::flags::SectionCharacteristics;
+use crate::utils::{read_u16, read_u32};
+use crate::PEResult;
+
+/// A single entry in the section table, describing one section of the image
+/// (e.g. `.text`, `.data`, `.rsrc`).
+#[derive(Debug)]
+pub(crate) struct SectionHeader {
+    pub(crate) name: [u8; 8],
+    pub(crate) virtual_size: u32,
+    pub(crate) virtual_address: u32,
+    pub(crate) size_of_raw_data: u32,
+    pub(crate) pointer_to_raw_data: u32,
+    pub(crate) pointer_to_relocations: u32,
+    pub(crate) pointer_to_linenumbers: u32,
+    pub(crate) number_of_relocations: u16,
+    pub(crate) number_of_linenumbers: u16,
+    pub(crate) characteristics: SectionCharacteristics,
+}
+
+/// Resolves a relative virtual address to a file offset by finding the
+/// section that contains it. RVAs that fall outside of every section (e.g. a
+/// data directory pointing into the headers) are returned unchanged, since
+/// that region is laid out identically in both the file and the loaded
+/// image.
+pub(crate) fn rva_to_offset(sections: &[SectionHeader], rva: u32) -> u64 {
+    for section in sections {
+        let section_size = section.virtual_size.max(section.size_of_raw_data);
+        let start = section.virtual_address;
+
+        // `start + section_size` can overflow for a crafted header; treat
+        // overflow the same as "rva isn't in this section" instead of
+        // panicking.
+        let end = match start.checked_add(section_size) {
+            Some(end) => end,
+            None => continue,
+        };
+
+        if start <= rva && rva < end {
+            return u64::from(section.pointer_to_raw_data) + u64::from(rva - start);
+        }
+    }
+
+    u64::from(rva)
+}
+
+impl SectionHeader {
+    pub(crate) fn parse(buf: &mut dyn Read) -> PEResult<Self> {
+        let mut name = [0_u8; 8];
+        buf.read_exact(&mut name)?;
+
+        let virtual_size = read_u32(buf)?;
+        let virtual_address = read_u32(buf)?;
+        let size_of_raw_data = read_u32(buf)?;
+        let pointer_to_raw_data = read_u32(buf)?;
+        let pointer_to_relocations = read_u32(buf)?;
+        let pointer_to_linenumbers = read_u32(buf)?;
+        let number_of_relocations = read_u16(buf)?;
+        let number_of_linenumbers = read_u16(buf)?;
+        let characteristics = SectionCharacteristics(read_u32(buf)?);
+
+        Ok(SectionHeader {
+            name,
+            virtual_size,
+            virtual_address,
+            size_of_raw_data,
+            pointer_to_raw_data,
+            pointer_to_relocations,
+            pointer_to_linenumbers,
+            number_of_relocations,
+            number_of_linenumbers,
+            characteristics,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn section(virtual_address: u32, virtual_size: u32, pointer_to_raw_data: u32) -> SectionHeader {
+        SectionHeader {
+            name: *b".text\0\0\0",
+            virtual_size,
+            virtual_address,
+            size_of_raw_data: 0,
+            pointer_to_raw_data,
+            pointer_to_relocations: 0,
+            pointer_to_linenumbers: 0,
+            number_of_relocations: 0,
+            number_of_linenumbers: 0,
+            characteristics: SectionCharacteristics(0),
+        }
+    }
+
+    #[test]
+    fn rva_to_offset_skips_section_whose_bounds_overflow_instead_of_panicking() {
+        // `virtual_address + virtual_size` overflows a u32; this must be
+        // treated as "rva not in this section", not panic.
+        let sections = vec![section(u32::MAX - 0x10, 0x1000, 0)];
+
+        assert_eq!(rva_to_offset(&sections, 0x1000), 0x1000);
+    }
+
+    #[test]
+    fn rva_to_offset_finds_containing_section() {
+        let sections = vec![section(0x1000, 0x200, 0x400)];
+
+        assert_eq!(rva_to_offset(&sections, 0x1010), 0x410);
+    }
+}
@@ -1,4 +1,6 @@
-use std::io::{self, Read};
+use std::io::{self, Read, Seek, SeekFrom};
+
+use crate::{PEError, PEResult};
 
 pub(crate) fn read_u8(buf: &mut dyn Read) -> io::Result<u8> {
     let mut buffer = [0_u8];
@@ -20,6 +22,66 @@ pub(crate) fn read_u32(buf: &mut dyn Read) -> io::Result<u32> {
     Ok(u32::from_le_bytes(buffer))
 }
 
+/// Read 8 bytes as a u64
+pub(crate) fn read_u64(buf: &mut dyn Read) -> io::Result<u64> {
+    let mut buffer = [0_u8; 8];
+    buf.read_exact(&mut buffer)?;
+    Ok(u64::from_le_bytes(buffer))
+}
+
+/// Read a little-endian u16 out of `bytes` at `offset`, bounds-checked.
+pub(crate) fn read_u16_at(bytes: &[u8], offset: usize) -> PEResult<u16> {
+    let slice = slice_at(bytes, offset, 2)?;
+    Ok(u16::from_le_bytes([slice[0], slice[1]]))
+}
+
+/// Read a little-endian u32 out of `bytes` at `offset`, bounds-checked.
+pub(crate) fn read_u32_at(bytes: &[u8], offset: usize) -> PEResult<u32> {
+    let slice = slice_at(bytes, offset, 4)?;
+    Ok(u32::from_le_bytes([slice[0], slice[1], slice[2], slice[3]]))
+}
+
+/// Read a little-endian u64 out of `bytes` at `offset`, bounds-checked.
+pub(crate) fn read_u64_at(bytes: &[u8], offset: usize) -> PEResult<u64> {
+    let slice = slice_at(bytes, offset, 8)?;
+    let mut buffer = [0_u8; 8];
+    buffer.copy_from_slice(slice);
+    Ok(u64::from_le_bytes(buffer))
+}
+
+fn slice_at(bytes: &[u8], offset: usize, len: usize) -> PEResult<&[u8]> {
+    let end = offset.checked_add(len).ok_or(PEError::UnexpectedEof)?;
+    bytes.get(offset..end).ok_or(PEError::UnexpectedEof)
+}
+
+/// The number of bytes left to read from the current position to the end
+/// of the stream. Used to cap preallocation of attacker-controlled counts
+/// (e.g. array lengths read straight from a file header) so a crafted
+/// header can't force a multi-gigabyte single-shot allocation.
+pub(crate) fn remaining_len(buf: &mut (impl Read + Seek)) -> PEResult<u64> {
+    let current = buf.stream_position()?;
+    let end = buf.seek(SeekFrom::End(0))?;
+    buf.seek(SeekFrom::Start(current))?;
+
+    Ok(end.saturating_sub(current))
+}
+
+/// Read a NUL-terminated string, stopping at (and discarding) the
+/// terminating byte. Invalid UTF-8 is replaced with `U+FFFD`.
+pub(crate) fn read_cstring(buf: &mut dyn Read) -> io::Result<String> {
+    let mut bytes = Vec::new();
+
+    loop {
+        let byte = read_u8(buf)?;
+        if byte == 0 {
+            break;
+        }
+        bytes.push(byte);
+    }
+
+    Ok(String::from_utf8_lossy(&bytes).into_owned())
+}
+
 /// Read `n` bytes as [u8; n]
 /// This is a hack until const generics
 #[macro_export]